@@ -0,0 +1,195 @@
+//! A tiny text assembler for Chip-8 programs
+//!
+//! This is the inverse of `instruction::disassemble`: it parses the same
+//! mnemonics `Instruction`'s `Display` impl renders (`LD V1, 0x11`,
+//! `DRW V1, V2, 3`, `JP 0x200`, ...) back into bytes, so ROMs disassembled
+//! for inspection can be hand-edited and reassembled.
+
+use std::collections::HashMap;
+use instruction::{Instruction, Addr};
+
+/// An error encountered while assembling a source line
+#[derive(Debug, Clone, PartialEq)]
+pub enum AsmError {
+	/// No instruction is named by this mnemonic
+	UnknownMnemonic(String),
+	/// A label was referenced but never defined
+	UnknownLabel(String),
+	/// An operand could not be parsed
+	BadOperand(String),
+}
+
+/// Assemble `src` into raw Chip-8 bytes, starting at `0x200`
+///
+/// Supports one label per line (`loop:`) and label operands on `JP`,
+/// `CALL` and `LD I, <label>`, resolved in a first pass over the source.
+pub fn assemble(src: &str) -> Result<Vec<u8>, AsmError> {
+	let lines: Vec<&str> = src.lines()
+		.map(|line| line.split(';').next().unwrap().trim())
+		.filter(|line| !line.is_empty())
+		.collect();
+
+	let mut labels = HashMap::new();
+	let mut addr: Addr = 0x200;
+	for line in &lines {
+		if let Some(label) = line.strip_suffix(':') {
+			labels.insert(label.to_string(), addr);
+		} else {
+			addr += 2;
+		}
+	}
+
+	let mut out = Vec::new();
+	for line in &lines {
+		if line.ends_with(':') {
+			continue;
+		}
+		let instr = parse_line(line, &labels)?;
+		let opcode = instr.encode();
+		out.push((opcode >> 8) as u8);
+		out.push((opcode & 0xff) as u8);
+	}
+	Ok(out)
+}
+
+fn parse_reg(arg: &str) -> Result<u8, AsmError> {
+	let arg = arg.trim();
+	let digits = arg.strip_prefix('V').or_else(|| arg.strip_prefix('v'));
+	match digits {
+		Some(digits) => u8::from_str_radix(digits, 16).map_err(|_| AsmError::BadOperand(arg.to_string())),
+		None => Err(AsmError::BadOperand(arg.to_string())),
+	}
+}
+
+fn parse_num(arg: &str) -> Result<u16, AsmError> {
+	let arg = arg.trim();
+	let hex = arg.strip_prefix("0x").or_else(|| arg.strip_prefix("0X"));
+	match hex {
+		Some(digits) => u16::from_str_radix(digits, 16).map_err(|_| AsmError::BadOperand(arg.to_string())),
+		None => arg.parse().map_err(|_| AsmError::BadOperand(arg.to_string())),
+	}
+}
+
+fn parse_addr(arg: &str, labels: &HashMap<String, Addr>) -> Result<Addr, AsmError> {
+	let arg = arg.trim();
+	match labels.get(arg) {
+		Some(&addr) => Ok(addr),
+		None => parse_num(arg),
+	}
+}
+
+/// Fetch operand `n`, or a `BadOperand` error naming the whole operand list if it's missing
+fn op<'a>(ops: &[&'a str], n: usize) -> Result<&'a str, AsmError> {
+	ops.get(n).cloned().ok_or_else(|| AsmError::BadOperand(ops.join(",")))
+}
+
+fn parse_line(line: &str, labels: &HashMap<String, Addr>) -> Result<Instruction, AsmError> {
+	use self::Instruction::*;
+
+	let mut parts = line.splitn(2, char::is_whitespace);
+	let mnemonic = parts.next().unwrap_or("").to_uppercase();
+	let rest = parts.next().unwrap_or("").trim();
+	let ops: Vec<&str> = if rest.is_empty() {
+		Vec::new()
+	} else {
+		rest.split(',').map(|op| op.trim()).collect()
+	};
+
+	match mnemonic.as_str() {
+		"SYS" => Ok(Sys(parse_addr(op(&ops, 0)?, labels)?)),
+		"CLS" => Ok(Cls),
+		"RET" => Ok(Ret),
+		"JP" if ops.len() == 1 => Ok(Jp(parse_addr(op(&ops, 0)?, labels)?)),
+		"JP" => Ok(JpV0(parse_addr(op(&ops, 1)?, labels)?)),
+		"CALL" => Ok(Call(parse_addr(op(&ops, 0)?, labels)?)),
+		"SE" => {
+			let (a, b) = (op(&ops, 0)?, op(&ops, 1)?);
+			if b.to_uppercase().starts_with('V') {
+				Ok(SeReg(parse_reg(a)?, parse_reg(b)?))
+			} else {
+				Ok(Se(parse_reg(a)?, parse_num(b)? as u8))
+			}
+		}
+		"SNE" => {
+			let (a, b) = (op(&ops, 0)?, op(&ops, 1)?);
+			if b.to_uppercase().starts_with('V') {
+				Ok(SneReg(parse_reg(a)?, parse_reg(b)?))
+			} else {
+				Ok(Sne(parse_reg(a)?, parse_num(b)? as u8))
+			}
+		}
+		"ADD" => {
+			let (a, b) = (op(&ops, 0)?, op(&ops, 1)?);
+			if a.to_uppercase() == "I" {
+				Ok(AddI(parse_reg(b)?))
+			} else if b.to_uppercase().starts_with('V') {
+				Ok(AddCarry(parse_reg(a)?, parse_reg(b)?))
+			} else {
+				Ok(AddReg(parse_reg(a)?, parse_num(b)? as u8))
+			}
+		}
+		"OR" => Ok(Or(parse_reg(op(&ops, 0)?)?, parse_reg(op(&ops, 1)?)?)),
+		"AND" => Ok(And(parse_reg(op(&ops, 0)?)?, parse_reg(op(&ops, 1)?)?)),
+		"XOR" => Ok(Xor(parse_reg(op(&ops, 0)?)?, parse_reg(op(&ops, 1)?)?)),
+		"SUB" => Ok(Sub(parse_reg(op(&ops, 0)?)?, parse_reg(op(&ops, 1)?)?)),
+		"SUBN" => Ok(Subn(parse_reg(op(&ops, 0)?)?, parse_reg(op(&ops, 1)?)?)),
+		"SHR" => Ok(Shr(parse_reg(op(&ops, 0)?)?, parse_reg(op(&ops, 1)?)?)),
+		"SHL" => Ok(Shl(parse_reg(op(&ops, 0)?)?, parse_reg(op(&ops, 1)?)?)),
+		"RND" => Ok(Rnd(parse_reg(op(&ops, 0)?)?, parse_num(op(&ops, 1)?)? as u8)),
+		"DRW" => Ok(Drw(parse_reg(op(&ops, 0)?)?, parse_reg(op(&ops, 1)?)?, parse_num(op(&ops, 2)?)? as u8)),
+		"SKP" => Ok(Skp(parse_reg(op(&ops, 0)?)?)),
+		"SKNP" => Ok(Sknp(parse_reg(op(&ops, 0)?)?)),
+		"LD" => parse_ld(&ops, labels),
+		_ => Err(AsmError::UnknownMnemonic(mnemonic)),
+	}
+}
+
+fn parse_ld(ops: &[&str], labels: &HashMap<String, Addr>) -> Result<Instruction, AsmError> {
+	use self::Instruction::*;
+
+	if ops.len() != 2 {
+		return Err(AsmError::BadOperand(ops.join(",")));
+	}
+	let (dst, src) = (ops[0], ops[1]);
+	let dst_upper = dst.to_uppercase();
+	let src_upper = src.to_uppercase();
+
+	match (dst_upper.as_str(), src_upper.as_str()) {
+		("I", _) => Ok(LdI(parse_addr(src, labels)?)),
+		("DT", _) => Ok(LdDelayTimerReg(parse_reg(src)?)),
+		("ST", _) => Ok(LdSoundTimer(parse_reg(src)?)),
+		("F", _) => Ok(LdSprite(parse_reg(src)?)),
+		("B", _) => Ok(LdBCD(parse_reg(src)?)),
+		("[I]", _) => Ok(LdStoreV0(parse_reg(src)?)),
+		(_, "DT") => Ok(LdDelayTimerValue(parse_reg(dst)?)),
+		(_, "K") => Ok(LdKeypress(parse_reg(dst)?)),
+		(_, "[I]") => Ok(LdReadV0(parse_reg(dst)?)),
+		(_, _) if src_upper.starts_with('V') => Ok(LdReg(parse_reg(dst)?, parse_reg(src)?)),
+		_ => Ok(Ld(parse_reg(dst)?, parse_num(src)? as u8)),
+	}
+}
+
+#[test]
+fn test_assemble_basic_mnemonics() {
+	let bytes = assemble("LD V0, 0x11\nLD I, 0x300\nDRW V0, V1, 5").unwrap();
+	assert_eq!(bytes, vec![0x60, 0x11, 0xa3, 0x00, 0xd0, 0x15]);
+}
+
+#[test]
+fn test_assemble_resolves_labels() {
+	let bytes = assemble("loop:\nJP loop").unwrap();
+	assert_eq!(bytes, vec![0x12, 0x00]);
+}
+
+#[test]
+fn test_assemble_unknown_mnemonic() {
+	assert_eq!(assemble("FROB V0, V1"), Err(AsmError::UnknownMnemonic("FROB".to_string())));
+}
+
+#[test]
+fn test_assemble_missing_operand_is_an_error() {
+	assert_eq!(assemble("SE V0"), Err(AsmError::BadOperand("V0".to_string())));
+	assert_eq!(assemble("ADD V0"), Err(AsmError::BadOperand("V0".to_string())));
+	assert_eq!(assemble("DRW V0, V1"), Err(AsmError::BadOperand("V0,V1".to_string())));
+	assert_eq!(assemble("SYS"), Err(AsmError::BadOperand("".to_string())));
+}
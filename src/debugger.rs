@@ -0,0 +1,219 @@
+//! Interactive step-debugger for a `System`
+//!
+//! Gives a REPL-style session similar to moa's debugger: set PC
+//! breakpoints, single-step, run until a breakpoint is hit, and inspect
+//! the machine's registers, stack, display and memory.
+
+use System;
+use instruction::Instruction;
+
+/// Wraps a `System` and drives it one instruction at a time
+pub struct Debugger {
+	/// PC values that halt `continue`
+	breakpoints: Vec<u16>,
+	/// Print every instruction before it is executed
+	pub trace: bool,
+}
+
+impl Debugger {
+	/// Creates a new Debugger with no breakpoints set
+	pub fn new() -> Debugger {
+		Debugger {
+			breakpoints: Vec::new(),
+			trace: false,
+		}
+	}
+
+	/// Set a breakpoint at `addr`
+	pub fn add_breakpoint(&mut self, addr: u16) {
+		self.breakpoints.push(addr);
+	}
+
+	/// Remove a previously set breakpoint
+	pub fn remove_breakpoint(&mut self, addr: u16) {
+		self.breakpoints.retain(|&b| b != addr);
+	}
+
+	fn is_breakpoint(&self, addr: u16) -> bool {
+		self.breakpoints.contains(&addr)
+	}
+
+	/// Advance `system` by exactly one instruction, tracing it if enabled
+	fn step_once(&self, system: &mut System) -> Option<Instruction> {
+		let pc = system.pc;
+		match system.step() {
+			Ok(instr) => {
+				if self.trace {
+					println!("0x{:03X}: {:?}", pc, instr);
+				}
+				Some(instr)
+			}
+			Err(err) => {
+				println!("{}", err);
+				None
+			}
+		}
+	}
+
+	/// Advance `system` by `count` instructions, stopping early if decoding fails
+	pub fn step(&self, system: &mut System, count: usize) {
+		for _ in 0..count {
+			if self.step_once(system).is_none() {
+				break;
+			}
+		}
+	}
+
+	/// Run `system` until a breakpoint is hit or decoding fails
+	pub fn run_until_breakpoint(&self, system: &mut System) {
+		loop {
+			if self.step_once(system).is_none() {
+				break;
+			}
+			if self.is_breakpoint(system.pc) {
+				println!("breakpoint hit at 0x{:03X}", system.pc);
+				break;
+			}
+		}
+	}
+
+	/// Dump the registers, stack and display of `system`
+	pub fn dump_regs(&self, system: &System) {
+		println!("pc: 0x{:03X}  i: 0x{:03X}  sp: {}  dt: {}  st: {}", system.pc, system.i, system.sp, system.dt, system.st);
+		for (i, reg) in system.regs.iter().enumerate() {
+			println!("v{:x}: 0x{:02X}", i, reg);
+		}
+		println!("stack: {:?}", system.stack);
+		println!("{:?}", system.display);
+	}
+
+	/// Print a hex view of `len` bytes of `system.mem` starting at `start`
+	pub fn dump_mem(&self, system: &System, start: usize, len: usize) {
+		let end = match start.checked_add(len) {
+			Some(end) if end <= system.mem.len() => end,
+			_ => {
+				println!("out of range: mem is 0x{:03X} bytes, requested 0x{:03X}..+{}", system.mem.len(), start, len);
+				return;
+			}
+		};
+		for (offset, byte) in system.mem[start..end].iter().enumerate() {
+			if offset % 16 == 0 {
+				print!("\n0x{:03X}: ", start + offset);
+			}
+			print!("{:02X} ", byte);
+		}
+		println!();
+	}
+
+	/// Parse a trailing repeat-count argument, like moa's `check_repeat_arg`,
+	/// so `step 20` advances twenty instructions instead of one
+	fn check_repeat_arg(args: &[&str], index: usize) -> usize {
+		args.get(index).and_then(|arg| arg.parse().ok()).unwrap_or(1)
+	}
+
+	fn parse_addr(arg: &str) -> Option<u16> {
+		u16::from_str_radix(arg.trim_start_matches("0x"), 16).ok()
+	}
+
+	/// Parse and execute a single debugger command
+	pub fn run_command(&mut self, system: &mut System, args: &[&str]) {
+		match args {
+			["break", addr] | ["b", addr] => {
+				match Self::parse_addr(addr) {
+					Some(addr) => {
+						self.add_breakpoint(addr);
+						println!("breakpoint set at 0x{:03X}", addr);
+					}
+					None => println!("invalid address: {}", addr),
+				}
+			}
+			["delete", addr] => {
+				if let Some(addr) = Self::parse_addr(addr) {
+					self.remove_breakpoint(addr);
+				}
+			}
+			["step"] | ["s"] => self.step(system, 1),
+			["step", _] | ["s", _] => self.step(system, Self::check_repeat_arg(args, 1)),
+			["continue"] | ["c"] => self.run_until_breakpoint(system),
+			["trace"] => {
+				self.trace = !self.trace;
+				println!("trace {}", if self.trace { "on" } else { "off" });
+			}
+			["regs"] | ["r"] => self.dump_regs(system),
+			["mem", start, len] => {
+				match (Self::parse_addr(start), len.parse()) {
+					(Some(start), Ok(len)) => self.dump_mem(system, start as usize, len),
+					_ => println!("usage: mem <start> <len>"),
+				}
+			}
+			_ => println!("unknown command: {:?}", args),
+		}
+	}
+}
+
+#[test]
+fn test_step_advances_pc_by_count() {
+	let mut sys = System::new();
+	sys.pc = 0x200;
+	// NOP-ish: LD V0, 0x00, three times
+	for addr in [0x200, 0x202, 0x204] {
+		sys.mem[addr] = 0x60;
+		sys.mem[addr + 1] = 0x00;
+	}
+	let debugger = Debugger::new();
+	debugger.step(&mut sys, 3);
+	assert_eq!(sys.pc, 0x206);
+}
+
+#[test]
+fn test_run_until_breakpoint_stops_at_breakpoint() {
+	let mut sys = System::new();
+	sys.pc = 0x200;
+	sys.mem[0x200] = 0x60; // LD V0, 0x00
+	sys.mem[0x201] = 0x00;
+	sys.mem[0x202] = 0x61; // LD V1, 0x00
+	sys.mem[0x203] = 0x00;
+
+	let mut debugger = Debugger::new();
+	debugger.add_breakpoint(0x202);
+	debugger.run_until_breakpoint(&mut sys);
+	assert_eq!(sys.pc, 0x202);
+}
+
+#[test]
+fn test_remove_breakpoint() {
+	let mut debugger = Debugger::new();
+	debugger.add_breakpoint(0x200);
+	assert!(debugger.is_breakpoint(0x200));
+	debugger.remove_breakpoint(0x200);
+	assert!(!debugger.is_breakpoint(0x200));
+}
+
+#[test]
+fn test_run_command_sets_breakpoint() {
+	let mut sys = System::new();
+	let mut debugger = Debugger::new();
+	debugger.run_command(&mut sys, &["break", "0x300"]);
+	assert!(debugger.is_breakpoint(0x300));
+}
+
+#[test]
+fn test_run_command_step_with_repeat_count() {
+	let mut sys = System::new();
+	sys.pc = 0x200;
+	for addr in [0x200, 0x202] {
+		sys.mem[addr] = 0x60;
+		sys.mem[addr + 1] = 0x00;
+	}
+	let mut debugger = Debugger::new();
+	debugger.run_command(&mut sys, &["step", "2"]);
+	assert_eq!(sys.pc, 0x204);
+}
+
+#[test]
+fn test_dump_mem_out_of_range_does_not_panic() {
+	let sys = System::new();
+	let debugger = Debugger::new();
+	// Should print an error and return instead of indexing out of bounds
+	debugger.dump_mem(&sys, 0xfff, 100);
+}
@@ -0,0 +1,42 @@
+//! Error types for decoding and loading Chip-8 programs
+
+use std::fmt;
+use std::io;
+
+/// Errors that can occur while decoding or running a Chip-8 program
+#[derive(Debug)]
+pub enum Chip8Error {
+	/// An I/O error occurred while reading a ROM
+	Io(io::Error),
+	/// No instruction matches this opcode
+	UnknownOpcode {
+		opcode: u16,
+		/// The PC the opcode was fetched from, if known
+		pc: Option<u16>,
+	},
+	/// A ROM is too large to fit in memory starting at 0x200
+	RomTooLarge,
+}
+
+impl fmt::Display for Chip8Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			Chip8Error::Io(ref err) => write!(f, "I/O error: {}", err),
+			Chip8Error::UnknownOpcode { opcode, pc: Some(pc) } => {
+				write!(f, "unknown opcode 0x{:04X} at 0x{:03X}", opcode, pc)
+			}
+			Chip8Error::UnknownOpcode { opcode, pc: None } => {
+				write!(f, "unknown opcode 0x{:04X}", opcode)
+			}
+			Chip8Error::RomTooLarge => write!(f, "ROM is too large to fit in memory"),
+		}
+	}
+}
+
+impl std::error::Error for Chip8Error {}
+
+impl From<io::Error> for Chip8Error {
+	fn from(err: io::Error) -> Chip8Error {
+		Chip8Error::Io(err)
+	}
+}
@@ -5,6 +5,7 @@ use std::io::Read;
 use std::fs::File;
 use std::io;
 use std::path::Path;
+use error::Chip8Error;
 
 
 /// A 12bit value
@@ -126,7 +127,7 @@ fn get_nibble(val: u16) -> Nibble {
 
 impl Instruction {
 	/// Get the instruction to the value
-	pub fn parse(val: u16) -> Option<Instruction> {
+	pub fn parse(val: u16) -> Result<Instruction, Chip8Error> {
 		use self::Instruction::*;
 		let x = (
 			(val & 0xf000) >> 12,
@@ -137,159 +138,246 @@ impl Instruction {
 		// For debugging
 		//println!("{:X}", val);
 		match x {
-			(0x0, 0x0, 0xe, 0x0) => Some(Cls),
-			(0x0, 0x0, 0xe, 0xe) => Some(Ret),
-			(0x0, _  , _  , _  ) => Some(Sys(get_addr(val))),
+			(0x0, 0x0, 0xe, 0x0) => Ok(Cls),
+			(0x0, 0x0, 0xe, 0xe) => Ok(Ret),
+			(0x0, _  , _  , _  ) => Ok(Sys(get_addr(val))),
 			(0x1, _  , _  , _  ) => {
 				let masked = get_addr(val);
-				Some(Jp(masked))
+				Ok(Jp(masked))
 			},
 			(0x2, _  , _  , _  ) => {
 				let masked = get_addr(val);
-				Some(Call(masked))
+				Ok(Call(masked))
 			},
 			(0x3, _  , _  , _  ) => {
 				let register = get_x(val);
 				let value = get_byte(val);
-				Some(Se(register, value))
+				Ok(Se(register, value))
 			},
 			(0x4, _  , _  , _  ) => {
 				let register = get_x(val);
 				let value = get_byte(val);
-				Some(Sne(register, value))
+				Ok(Sne(register, value))
 			},
 			(0x5, _  , _  , 0x0) => {
 				let x = get_x(val);
 				let y = get_y(val);
-				Some(SeReg(x, y))
+				Ok(SeReg(x, y))
 			},
 			(0x6, _  , _  , _ ) => {
 				let register = get_x(val);
 				let value = get_byte(val);
-				Some(Ld(register, value))
+				Ok(Ld(register, value))
 			},
 			(0x7, _  , _  , _ ) => {
 				let register = get_x(val);
 				let value = get_byte(val);
-				Some(AddReg(register, value))
+				Ok(AddReg(register, value))
 			}
 			(0x8, _  , _  , 0x0 ) => {
 				let x = get_x(val);
 				let y = get_y(val);
-				Some(LdReg(x, y))
+				Ok(LdReg(x, y))
 			},
 			(0x8, _  , _  , 0x1 ) => {
 				let x = get_x(val);
 				let y = get_y(val);
-				Some(Or(x, y))
+				Ok(Or(x, y))
 			},
 			(0x8, _  , _  , 0x2 ) => {
 				let x = get_x(val);
 				let y = get_y(val);
-				Some(And(x, y))
+				Ok(And(x, y))
 			},
 			(0x8, _  , _  , 0x3 ) => {
 				let x = get_x(val);
 				let y = get_y(val);
-				Some(Xor(x, y))
+				Ok(Xor(x, y))
 			},
 			(0x8, _  , _  , 0x4 ) => {
 				let x = get_x(val);
 				let y = get_y(val);
-				Some(AddCarry(x, y))
+				Ok(AddCarry(x, y))
 			},
 			(0x8, _  , _  , 0x5 ) => {
 				let x = get_x(val);
 				let y = get_y(val);
-				Some(Sub(x, y))
+				Ok(Sub(x, y))
 			},
 			(0x8, _  , _  , 0x6 ) => {
 				let x = get_x(val);
 				let y = get_y(val);
-				Some(Shr(x, y))
+				Ok(Shr(x, y))
 			},
 			(0x8, _  , _  , 0x7 ) => {
 				let x = get_x(val);
 				let y = get_y(val);
-				Some(Subn(x, y))
+				Ok(Subn(x, y))
 			},
 			(0x8, _  , _  , 0xe ) => {
 				let x = get_x(val);
 				let y = get_y(val);
-				Some(Shl(x, y))
+				Ok(Shl(x, y))
 			},
 			(0x9, _  , _  , 0x0 ) => {
 				let x = get_x(val);
 				let y = get_y(val);
-				Some(SneReg(x, y))
+				Ok(SneReg(x, y))
 			},
 			(0xa, _  , _  , _ ) => {
 				let addr = get_addr(val);
-				Some(LdI(addr))
+				Ok(LdI(addr))
 			},
 			(0xb, _  , _  , _ ) => {
 				let addr = get_addr(val);
-				Some(JpV0(addr))
+				Ok(JpV0(addr))
 			},
 			(0xc, _  , _  , _ ) => {
 				let x = get_x(val);
 				let y = get_byte(val);
-				Some(Rnd(x, y))
+				Ok(Rnd(x, y))
 			},
 			(0xd, _  , _  , _ ) => {
 				let x = get_x(val);
 				let y = get_y(val);
 				let n = get_nibble(val);
-				Some(Drw(x, y, n))
+				Ok(Drw(x, y, n))
 			},
 			(0xe, _ , 0x9, 0xe) => {
 				let x = get_x(val);
-				Some(Skp(x))
+				Ok(Skp(x))
 			},
 			(0xe, _ , 0xa, 0x1) => {
 				let x = get_x(val);
-				Some(Sknp(x))
+				Ok(Sknp(x))
 			},
 			(0xf, _ , 0x0, 0x7) => {
 				let x = get_x(val);
-				Some(LdDelayTimerValue(x))
+				Ok(LdDelayTimerValue(x))
 			},
 			(0xf, _ , 0x0, 0xa) => {
 				let x = get_x(val);
-				Some(LdKeypress(x))
+				Ok(LdKeypress(x))
 			},
 			(0xf, _ , 0x1, 0x5) => {
 				let x = get_x(val);
-				Some(LdDelayTimerReg(x))
+				Ok(LdDelayTimerReg(x))
 			},
 			(0xf, _ , 0x1, 0x8) => {
 				let x = get_x(val);
-				Some(LdSoundTimer(x))
+				Ok(LdSoundTimer(x))
 			},
 			(0xf, _ , 0x1, 0xe) => {
 				let x = get_x(val);
-				Some(AddI(x))
+				Ok(AddI(x))
 			},
 			(0xf, _ , 0x2, 0x9) => {
 				let x = get_x(val);
-				Some(LdSprite(x))
+				Ok(LdSprite(x))
 			},
 			(0xf, _ , 0x3, 0x3) => {
 				let x = get_x(val);
-				Some(LdBCD(x))
+				Ok(LdBCD(x))
 			},
 			(0xf, _ , 0x5, 0x5) => {
 				let x = get_x(val);
-				Some(LdStoreV0(x))
+				Ok(LdStoreV0(x))
 			},
 			(0xf, _ , 0x6, 0x5) => {
 				let x = get_x(val);
-				Some(LdReadV0(x))
+				Ok(LdReadV0(x))
 			},
-			_ => {
-				println!("Not implemented: {:?}", x);
-				None
-			}
+			_ => Err(Chip8Error::UnknownOpcode { opcode: val, pc: None }),
+		}
+	}
+
+	/// Reassemble this instruction back into its opcode word, the inverse of `parse`
+	pub fn encode(&self) -> u16 {
+		use self::Instruction::*;
+		match *self {
+			Sys(addr) => addr,
+			Cls => 0x00e0,
+			Ret => 0x00ee,
+			Jp(addr) => 0x1000 | addr,
+			Call(addr) => 0x2000 | addr,
+			Se(x, kk) => 0x3000 | (x as u16) << 8 | kk as u16,
+			Sne(x, kk) => 0x4000 | (x as u16) << 8 | kk as u16,
+			SeReg(x, y) => 0x5000 | (x as u16) << 8 | (y as u16) << 4,
+			Ld(x, kk) => 0x6000 | (x as u16) << 8 | kk as u16,
+			AddReg(x, kk) => 0x7000 | (x as u16) << 8 | kk as u16,
+			LdReg(x, y) => 0x8000 | (x as u16) << 8 | (y as u16) << 4,
+			Or(x, y) => 0x8001 | (x as u16) << 8 | (y as u16) << 4,
+			And(x, y) => 0x8002 | (x as u16) << 8 | (y as u16) << 4,
+			Xor(x, y) => 0x8003 | (x as u16) << 8 | (y as u16) << 4,
+			AddCarry(x, y) => 0x8004 | (x as u16) << 8 | (y as u16) << 4,
+			Sub(x, y) => 0x8005 | (x as u16) << 8 | (y as u16) << 4,
+			Shr(x, y) => 0x8006 | (x as u16) << 8 | (y as u16) << 4,
+			Subn(x, y) => 0x8007 | (x as u16) << 8 | (y as u16) << 4,
+			Shl(x, y) => 0x800e | (x as u16) << 8 | (y as u16) << 4,
+			SneReg(x, y) => 0x9000 | (x as u16) << 8 | (y as u16) << 4,
+			LdI(addr) => 0xa000 | addr,
+			JpV0(addr) => 0xb000 | addr,
+			Rnd(x, kk) => 0xc000 | (x as u16) << 8 | kk as u16,
+			Drw(x, y, n) => 0xd000 | (x as u16) << 8 | (y as u16) << 4 | n as u16,
+			Skp(x) => 0xe09e | (x as u16) << 8,
+			Sknp(x) => 0xe0a1 | (x as u16) << 8,
+			LdDelayTimerValue(x) => 0xf007 | (x as u16) << 8,
+			LdKeypress(x) => 0xf00a | (x as u16) << 8,
+			LdDelayTimerReg(x) => 0xf015 | (x as u16) << 8,
+			LdSoundTimer(x) => 0xf018 | (x as u16) << 8,
+			AddI(x) => 0xf01e | (x as u16) << 8,
+			LdSprite(x) => 0xf029 | (x as u16) << 8,
+			LdBCD(x) => 0xf033 | (x as u16) << 8,
+			LdStoreV0(x) => 0xf055 | (x as u16) << 8,
+			LdReadV0(x) => 0xf065 | (x as u16) << 8,
+		}
+	}
+
+	/// Render this instruction as an assembly mnemonic, e.g. `LD V1, 0x11`
+	pub fn to_asm(&self) -> String {
+		self.to_string()
+	}
+}
+
+impl std::fmt::Display for Instruction {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		use self::Instruction::*;
+		match *self {
+			Sys(addr) => write!(f, "SYS 0x{:03X}", addr),
+			Cls => write!(f, "CLS"),
+			Ret => write!(f, "RET"),
+			Jp(addr) => write!(f, "JP 0x{:03X}", addr),
+			Call(addr) => write!(f, "CALL 0x{:03X}", addr),
+			Se(x, kk) => write!(f, "SE V{:X}, 0x{:02X}", x, kk),
+			Sne(x, kk) => write!(f, "SNE V{:X}, 0x{:02X}", x, kk),
+			SeReg(x, y) => write!(f, "SE V{:X}, V{:X}", x, y),
+			Ld(x, kk) => write!(f, "LD V{:X}, 0x{:02X}", x, kk),
+			AddReg(x, kk) => write!(f, "ADD V{:X}, 0x{:02X}", x, kk),
+			LdReg(x, y) => write!(f, "LD V{:X}, V{:X}", x, y),
+			Or(x, y) => write!(f, "OR V{:X}, V{:X}", x, y),
+			And(x, y) => write!(f, "AND V{:X}, V{:X}", x, y),
+			Xor(x, y) => write!(f, "XOR V{:X}, V{:X}", x, y),
+			AddCarry(x, y) => write!(f, "ADD V{:X}, V{:X}", x, y),
+			Sub(x, y) => write!(f, "SUB V{:X}, V{:X}", x, y),
+			Shr(x, y) => write!(f, "SHR V{:X}, V{:X}", x, y),
+			Subn(x, y) => write!(f, "SUBN V{:X}, V{:X}", x, y),
+			Shl(x, y) => write!(f, "SHL V{:X}, V{:X}", x, y),
+			SneReg(x, y) => write!(f, "SNE V{:X}, V{:X}", x, y),
+			LdI(addr) => write!(f, "LD I, 0x{:03X}", addr),
+			JpV0(addr) => write!(f, "JP V0, 0x{:03X}", addr),
+			Rnd(x, kk) => write!(f, "RND V{:X}, 0x{:02X}", x, kk),
+			Drw(x, y, n) => write!(f, "DRW V{:X}, V{:X}, {}", x, y, n),
+			Skp(x) => write!(f, "SKP V{:X}", x),
+			Sknp(x) => write!(f, "SKNP V{:X}", x),
+			LdDelayTimerValue(x) => write!(f, "LD V{:X}, DT", x),
+			LdKeypress(x) => write!(f, "LD V{:X}, K", x),
+			LdDelayTimerReg(x) => write!(f, "LD DT, V{:X}", x),
+			LdSoundTimer(x) => write!(f, "LD ST, V{:X}", x),
+			AddI(x) => write!(f, "ADD I, V{:X}", x),
+			LdSprite(x) => write!(f, "LD F, V{:X}", x),
+			LdBCD(x) => write!(f, "LD B, V{:X}", x),
+			LdStoreV0(x) => write!(f, "LD [I], V{:X}", x),
+			LdReadV0(x) => write!(f, "LD V{:X}, [I]", x),
 		}
 	}
 }
@@ -305,11 +393,11 @@ impl<R> InstructionIterator<R> {
 	}
 }
 pub fn from_file<P: AsRef<Path>>(file: P) -> io::Result<InstructionIterator<File>> {
-	let file = File::open(file).unwrap();
+	let file = File::open(file)?;
 	Ok(InstructionIterator::new(file))
 }
 impl<T: Read> Iterator for InstructionIterator<T> {
-	type Item = Instruction;
+	type Item = Result<Instruction, Chip8Error>;
 	fn next(&mut self) -> Option<Self::Item> {
 		let mut data = [0u8; 2];
 		if self.reader.read_exact(&mut data).is_err() {
@@ -317,10 +405,22 @@ impl<T: Read> Iterator for InstructionIterator<T> {
 		}
 		let ins: u16 = ((data[0] as u16) << 8) + data[1] as u16;
 
-		Instruction::parse(ins)
+		Some(Instruction::parse(ins))
 	}
 }
 
+/// Disassemble a Chip-8 program, pairing each decoded `Instruction` with the
+/// address it was loaded at (programs are always loaded at `0x200`). Any
+/// opcode that fails to decode ends disassembly at that point.
+pub fn disassemble(bytes: &[u8]) -> Vec<(Addr, Instruction)> {
+	let iter = InstructionIterator::new(io::Cursor::new(bytes));
+	iter.enumerate()
+		.map(|(i, result)| result.ok().map(|instr| (0x200 + i as Addr * 2, instr)))
+		.take_while(|entry| entry.is_some())
+		.map(|entry| entry.unwrap())
+		.collect()
+}
+
 #[allow(unused_macros)]
 macro_rules! test_instr {
 	($x:expr, $y:expr) => {
@@ -368,3 +468,37 @@ fn test_instructions() {
 	test_instr!(0xf155, LdStoreV0(0x1));
 	test_instr!(0xf165, LdReadV0(0x1));
 }
+
+#[test]
+fn test_encode_is_inverse_of_parse() {
+	use self::Instruction::*;
+
+	let instructions = [
+		Sys(0x234), Cls, Ret, Jp(0x234), Call(0x234),
+		Se(0x1, 0x23), Sne(0x1, 0x23), SeReg(0x1, 0x2),
+		Ld(0x1, 0x23), AddReg(0x1, 0x23), LdReg(0x1, 0x2),
+		Or(0x1, 0x2), And(0x1, 0x2), Xor(0x1, 0x2),
+		AddCarry(0x1, 0x2), Sub(0x1, 0x2), Shr(0x1, 0x2),
+		Subn(0x1, 0x2), Shl(0x1, 0x2), SneReg(0x1, 0x2),
+		LdI(0x234), JpV0(0x234), Rnd(0x1, 0x23), Drw(0x1, 0x2, 0x3),
+		Skp(0x1), Sknp(0x1), LdDelayTimerValue(0x1), LdKeypress(0x1),
+		LdDelayTimerReg(0x1), LdSoundTimer(0x1), AddI(0x1), LdSprite(0x1),
+		LdBCD(0x1), LdStoreV0(0x1), LdReadV0(0x1),
+	];
+
+	for instr in instructions.iter() {
+		let opcode = instr.encode();
+		assert_eq!(Instruction::parse(opcode).unwrap(), *instr);
+	}
+}
+
+#[test]
+fn test_disassemble_then_assemble_round_trips_bytes() {
+	let rom: Vec<u8> = vec![0x60, 0x11, 0xa3, 0x00, 0xd0, 0x15, 0x00, 0xee];
+	let asm: String = disassemble(&rom).iter()
+		.map(|(_, instr)| instr.to_asm())
+		.collect::<Vec<_>>()
+		.join("\n");
+	let reassembled = ::asm::assemble(&asm).unwrap();
+	assert_eq!(reassembled, rom);
+}
@@ -0,0 +1,200 @@
+//! A basic-block JIT recompiler for hot instruction sequences
+//!
+//! Modeled on SkVM's approach of building and caching compiled program
+//! fragments: a basic block is a maximal run of instructions starting at
+//! a given PC and ending at the first control-flow instruction (`Jp`,
+//! `Call`, `Ret`, `JpV0`, a skip, `Drw`, or a key-wait). Each block is
+//! decoded once into a chain of closures and cached by entry PC, so
+//! re-entering it skips fetch/decode entirely. This leaves room for a
+//! real native backend later without changing the dispatcher.
+//!
+//! Chip-8 ROMs can self-modify (`LdStoreV0`, `LdBCD`), so every cached
+//! block keeps a snapshot of the bytes it was compiled from; after each
+//! block runs, any cached block whose live bytes no longer match its
+//! snapshot is evicted, forcing a recompile the next time its PC is
+//! reached. `LdStoreV0`/`LdBCD` also always end a block, even though
+//! they're not control flow: otherwise a write targeting a later
+//! not-yet-executed instruction in the *same* block would run against
+//! steps that were pre-decoded before the write happened.
+//!
+//! `Jit` is kept separate from `System` (rather than a field on it) so
+//! `System` stays a plain `Copy` struct for save-state serialization.
+
+use System;
+use instruction::{Addr, Instruction};
+use error::Chip8Error;
+use std::collections::HashMap;
+
+/// A single compiled step: applies one already-decoded instruction
+type Step = Box<dyn Fn(&mut System)>;
+
+/// A compiled basic block: a chain of closures built from decoded
+/// instructions, plus the byte range and snapshot needed to detect
+/// self-modification
+struct CompiledBlock {
+	/// Address of the block's first instruction (inclusive)
+	start: Addr,
+	/// Address just past the block's last instruction (exclusive)
+	end: Addr,
+	/// One compiled step per decoded instruction, in order
+	steps: Vec<Step>,
+	/// `mem[start..end]` as it was when the block was compiled
+	snapshot: Vec<u8>,
+}
+
+impl CompiledBlock {
+	/// *true* if `system.mem[start..end]` no longer matches `snapshot`
+	fn is_stale(&self, system: &System) -> bool {
+		system.mem[self.start as usize..self.end as usize] != self.snapshot[..]
+	}
+}
+
+/// *true* if `instruction` ends a basic block: either it's control flow, or
+/// (`LdStoreV0`/`LdBCD`) it can write to `mem` and so might self-modify a
+/// later, already-decoded instruction in the same block
+fn ends_block(instruction: &Instruction) -> bool {
+	use instruction::Instruction::*;
+	matches!(instruction,
+		Jp(_) | JpV0(_) | Call(_) | Ret |
+		Se(..) | Sne(..) | SeReg(..) | SneReg(..) | Skp(_) | Sknp(_) |
+		Drw(..) | LdKeypress(_) |
+		LdStoreV0(_) | LdBCD(_))
+}
+
+/// Compiles and caches basic blocks, dispatching through the cache
+/// instead of fetching and decoding one instruction at a time
+pub struct Jit {
+	blocks: HashMap<Addr, CompiledBlock>,
+}
+
+impl Jit {
+	/// Creates a new Jit with an empty block cache
+	pub fn new() -> Jit {
+		Jit {
+			blocks: HashMap::new(),
+		}
+	}
+
+	/// Decode and compile the basic block starting at `system.pc`
+	fn compile(&mut self, system: &System) -> Result<(), Chip8Error> {
+		let start = system.pc;
+		let mut pc = start;
+		let mut steps: Vec<Step> = Vec::new();
+		loop {
+			let opcode = ((system.mem[pc as usize] as u16) << 8) | system.mem[pc as usize + 1] as u16;
+			let instr = Instruction::parse(opcode).map_err(|err| match err {
+				Chip8Error::UnknownOpcode { opcode, .. } => Chip8Error::UnknownOpcode { opcode, pc: Some(pc) },
+				other => other,
+			})?;
+			let stop = ends_block(&instr);
+			pc += 2;
+			let next_pc = pc;
+			steps.push(Box::new(move |system: &mut System| {
+				system.pc = next_pc;
+				system.apply(instr);
+			}));
+			if stop {
+				break;
+			}
+		}
+		let end = pc;
+		let snapshot = system.mem[start as usize..end as usize].to_vec();
+		self.blocks.insert(start, CompiledBlock { start, end, steps, snapshot });
+		Ok(())
+	}
+
+	/// Evict every cached block whose backing bytes no longer match the
+	/// snapshot taken when it was compiled
+	fn evict_stale(&mut self, system: &System) {
+		self.blocks.retain(|_, block| !block.is_stale(system));
+	}
+
+	/// Run the basic block starting at `system.pc`, compiling it first if
+	/// it isn't already cached (or was evicted as stale)
+	pub fn step(&mut self, system: &mut System) -> Result<(), Chip8Error> {
+		let pc = system.pc;
+		if !self.blocks.contains_key(&pc) {
+			self.compile(system)?;
+		}
+		let len = self.blocks[&pc].steps.len();
+		for i in 0..len {
+			(self.blocks[&pc].steps[i])(system);
+		}
+		self.evict_stale(system);
+		Ok(())
+	}
+
+}
+
+#[test]
+fn test_self_modifying_rom_matches_with_and_without_jit() {
+	let mut sys = System::new();
+	sys.pc = 0x200;
+	// Block B: LD V1, 0x05 ; JP 0x204
+	sys.mem[0x200] = 0x61;
+	sys.mem[0x201] = 0x05;
+	sys.mem[0x202] = 0x12;
+	sys.mem[0x203] = 0x04;
+	// Block C: LD I, 0x200 ; LD V0, 0x69 ; LD [I], V0 ; JP 0x200
+	// (overwrites block B's first byte, turning "LD V1, 0x05" into "LD V9, 0x05")
+	sys.mem[0x204] = 0xa2;
+	sys.mem[0x205] = 0x00;
+	sys.mem[0x206] = 0x60;
+	sys.mem[0x207] = 0x69;
+	sys.mem[0x208] = 0xf0;
+	sys.mem[0x209] = 0x55;
+	sys.mem[0x20a] = 0x12;
+	sys.mem[0x20b] = 0x00;
+
+	let mut reference = sys;
+	for _ in 0..8 {
+		reference.step().unwrap();
+	}
+
+	let mut jitted = sys;
+	let mut jit = Jit::new();
+	jit.step(&mut jitted).unwrap(); // block B, first pass
+	jit.step(&mut jitted).unwrap(); // block C up to its LdStoreV0 write, overwrites block B
+	jit.step(&mut jitted).unwrap(); // block C's remaining JP, its own separate block
+	jit.step(&mut jitted).unwrap(); // block B again, must notice it's stale
+
+	assert_eq!(jitted.regs, reference.regs);
+	assert_eq!(jitted.pc, reference.pc);
+	assert_eq!(jitted.i, reference.i);
+}
+
+#[test]
+fn test_self_modification_within_a_single_block_is_not_stale() {
+	let mut sys = System::new();
+	sys.pc = 0x200;
+	// LD I, 0x206 ; LD V0, 0x00 ; LD [I], V0 ; (overwrites the first byte
+	// at 0x206, turning "LD V2, 0xFF" into a no-op "SYS 0x0FF" before it's
+	// ever fetched — V2 should stay 0, not become 0xFF) ; JP 0x208 (so the
+	// block has a real end instead of running off into zeroed memory)
+	sys.mem[0x200] = 0xa2;
+	sys.mem[0x201] = 0x06;
+	sys.mem[0x202] = 0x60;
+	sys.mem[0x203] = 0x00;
+	sys.mem[0x204] = 0xf0;
+	sys.mem[0x205] = 0x55;
+	// LD V2, 0xFF
+	sys.mem[0x206] = 0x62;
+	sys.mem[0x207] = 0xff;
+	// JP 0x208
+	sys.mem[0x208] = 0x12;
+	sys.mem[0x209] = 0x08;
+
+	let mut reference = sys;
+	for _ in 0..5 {
+		reference.step().unwrap();
+	}
+
+	let mut jitted = sys;
+	let mut jit = Jit::new();
+	jit.step(&mut jitted).unwrap(); // LdI, Ld, LdStoreV0 (ends at the write)
+	jit.step(&mut jitted).unwrap(); // re-decodes 0x206 live, sees the write
+
+	assert_eq!(jitted.regs, reference.regs);
+	assert_eq!(jitted.pc, reference.pc);
+	assert_eq!(jitted.i, reference.i);
+}
@@ -1,11 +1,28 @@
 extern crate rand;
 
 pub mod instruction;
+pub mod debugger;
+pub mod asm;
+pub mod error;
+#[cfg(feature = "jit")]
+pub mod jit;
 
 use std::path::Path;
 use std::fs::File;
-use std::io::Read;
+use std::io::{BufRead, Read, Write};
 use instruction::Instruction;
+use error::Chip8Error;
+use debugger::Debugger;
+
+/// Magic bytes identifying a Chip-8 save-state file
+const SAVE_STATE_MAGIC: [u8; 4] = *b"C8SS";
+/// Save-state format version, bumped whenever the on-disk layout changes
+const SAVE_STATE_VERSION: u8 = 2;
+
+/// How often the delay/sound timers tick, per the Chip-8 spec
+fn timer_period() -> std::time::Duration {
+	std::time::Duration::from_nanos(1_000_000_000 / 60)
+}
 
 /// Describes the Chip-8 Display
 #[derive(Copy, Clone)]
@@ -94,6 +111,55 @@ fn bits(val: u8) -> [bool; 8] {
 }
 
 
+/// Toggles for the historically ambiguous Chip-8 opcodes
+///
+/// Different interpreters disagree on what `Shr`/`Shl`/`Subn`,
+/// `LdStoreV0`/`LdReadV0`, `And`/`Or`/`Xor`, `JpV0` and `Drw` should do at
+/// the edges, and ROMs are written assuming one behavior or the other.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Quirks {
+	/// `Shr`/`Shl` shift Vx in place instead of first copying Vy into Vx
+	pub shift_quirk: bool,
+	/// `LdStoreV0`/`LdReadV0` leave I unchanged instead of incrementing it by x+1
+	pub load_store_quirk: bool,
+	/// `And`/`Or`/`Xor` reset VF to 0
+	pub logic_quirk: bool,
+	/// `JpV0(nnn)` jumps to `xnn + Vx` (SCHIP) instead of `nnn + V0`
+	pub jump_quirk: bool,
+	/// `Drw` clips sprites at the screen edge instead of wrapping them around
+	pub clip_quirk: bool,
+}
+
+impl Quirks {
+	/// Behavior of the original COSMAC VIP interpreter
+	pub fn cosmac_vip() -> Quirks {
+		Quirks {
+			shift_quirk: false,
+			load_store_quirk: false,
+			logic_quirk: true,
+			jump_quirk: false,
+			clip_quirk: true,
+		}
+	}
+
+	/// Behavior expected by most SUPER-CHIP ROMs
+	pub fn schip() -> Quirks {
+		Quirks {
+			shift_quirk: true,
+			load_store_quirk: true,
+			logic_quirk: false,
+			jump_quirk: true,
+			clip_quirk: false,
+		}
+	}
+}
+
+impl Default for Quirks {
+	fn default() -> Quirks {
+		Quirks::cosmac_vip()
+	}
+}
+
 /// The Emulator System
 #[derive(Copy, Clone)]
 pub struct System {
@@ -117,6 +183,14 @@ pub struct System {
 	pub display: Display,
 	/// Keyboard
 	pub keyboard: Keyboard,
+	/// Compatibility toggles for ambiguous opcodes
+	pub quirks: Quirks,
+	/// How many CPU cycles `tick` runs per 1/60s of wall-clock time
+	pub cycles_per_frame: u32,
+	/// Wall-clock time banked towards the next CPU cycle
+	cycle_accumulator: std::time::Duration,
+	/// Wall-clock time banked towards the next timer decrement
+	timer_accumulator: std::time::Duration,
 }
 
 macro_rules! store_sprites {
@@ -179,6 +253,10 @@ impl System {
 			mem: mem,
 			display: Display::new(),
 			keyboard: Keyboard::new(),
+			quirks: Quirks::default(),
+			cycles_per_frame: 10,
+			cycle_accumulator: std::time::Duration::new(0, 0),
+			timer_accumulator: std::time::Duration::new(0, 0),
 		}
 	}
 	/// Get a reference to a specific (general purpose) register
@@ -187,14 +265,133 @@ impl System {
 	}
 
 	/// Read a Chip-8 program and put its content into the emulator's memory
-	pub fn fetch_file<P: AsRef<Path>>(&mut self, path: P) -> std::io::Result<()> {
-		let file = File::open(path)?;
-		for (i, byte) in file.bytes().filter_map(|x| x.ok()).enumerate() {
-			// println!("0x{:X}: {:X}", 0x200 + i, byte);
-			self.mem[0x200 + i] = byte;
+	pub fn fetch_file<P: AsRef<Path>>(&mut self, path: P) -> Result<(), Chip8Error> {
+		let mut file = File::open(path)?;
+		let mut rom = Vec::new();
+		file.read_to_end(&mut rom)?;
+		if rom.len() > self.mem.len() - 0x200 {
+			return Err(Chip8Error::RomTooLarge);
+		}
+		for (i, byte) in rom.iter().enumerate() {
+			self.mem[0x200 + i] = *byte;
+		}
+		Ok(())
+	}
+	/// Freeze the full machine state (registers, memory, display, keyboard,
+	/// ...) into a compact binary blob at `path`, like Nestur's save-states
+	pub fn save_state<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+		let mut file = File::create(path)?;
+		file.write_all(&SAVE_STATE_MAGIC)?;
+		file.write_all(&[SAVE_STATE_VERSION])?;
+
+		file.write_all(&self.regs)?;
+		file.write_all(&self.i.to_be_bytes())?;
+		file.write_all(&self.pc.to_be_bytes())?;
+		file.write_all(&[self.sp, self.dt, self.st])?;
+		for &slot in self.stack.iter() {
+			file.write_all(&slot.to_be_bytes())?;
+		}
+		file.write_all(&self.mem)?;
+		for row in self.display.arr.iter() {
+			for &pixel in row.iter() {
+				file.write_all(&[pixel as u8])?;
+			}
+		}
+		for &key in self.keyboard.keys.iter() {
+			file.write_all(&[key as u8])?;
+		}
+		let quirks = self.quirks;
+		file.write_all(&[
+			quirks.shift_quirk as u8,
+			quirks.load_store_quirk as u8,
+			quirks.logic_quirk as u8,
+			quirks.jump_quirk as u8,
+			quirks.clip_quirk as u8,
+		])?;
+		file.write_all(&self.cycles_per_frame.to_be_bytes())?;
+		Ok(())
+	}
+
+	/// Restore the full machine state from a snapshot written by `save_state`
+	pub fn load_state<P: AsRef<Path>>(&mut self, path: P) -> std::io::Result<()> {
+		let mut file = File::open(path)?;
+		let mut header = [0u8; 5];
+		file.read_exact(&mut header)?;
+		if header[0..4] != SAVE_STATE_MAGIC {
+			return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "not a chip-8 save state"));
+		}
+		if header[4] != SAVE_STATE_VERSION {
+			return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "unsupported save state version"));
+		}
+
+		let mut regs = [0u8; 16];
+		file.read_exact(&mut regs)?;
+
+		let mut u16_buf = [0u8; 2];
+		file.read_exact(&mut u16_buf)?;
+		let i = u16::from_be_bytes(u16_buf);
+		file.read_exact(&mut u16_buf)?;
+		let pc = u16::from_be_bytes(u16_buf);
+
+		let mut sp_dt_st = [0u8; 3];
+		file.read_exact(&mut sp_dt_st)?;
+		let (sp, dt, st) = (sp_dt_st[0], sp_dt_st[1], sp_dt_st[2]);
+
+		let mut stack = [0u16; 16];
+		for slot in stack.iter_mut() {
+			file.read_exact(&mut u16_buf)?;
+			*slot = u16::from_be_bytes(u16_buf);
+		}
+
+		let mut mem = [0u8; 4_096];
+		file.read_exact(&mut mem)?;
+
+		let mut display = Display::new();
+		let mut pixel = [0u8; 1];
+		for row in display.arr.iter_mut() {
+			for cell in row.iter_mut() {
+				file.read_exact(&mut pixel)?;
+				*cell = pixel[0] != 0;
+			}
+		}
+
+		let mut keyboard = Keyboard::new();
+		for key in keyboard.keys.iter_mut() {
+			file.read_exact(&mut pixel)?;
+			*key = pixel[0] != 0;
 		}
+
+		let mut quirk_bytes = [0u8; 5];
+		file.read_exact(&mut quirk_bytes)?;
+		let quirks = Quirks {
+			shift_quirk: quirk_bytes[0] != 0,
+			load_store_quirk: quirk_bytes[1] != 0,
+			logic_quirk: quirk_bytes[2] != 0,
+			jump_quirk: quirk_bytes[3] != 0,
+			clip_quirk: quirk_bytes[4] != 0,
+		};
+
+		let mut u32_buf = [0u8; 4];
+		file.read_exact(&mut u32_buf)?;
+		let cycles_per_frame = u32::from_be_bytes(u32_buf);
+
+		self.regs = regs;
+		self.i = i;
+		self.pc = pc;
+		self.sp = sp;
+		self.dt = dt;
+		self.st = st;
+		self.stack = stack;
+		self.mem = mem;
+		self.display = display;
+		self.keyboard = keyboard;
+		self.quirks = quirks;
+		self.cycles_per_frame = cycles_per_frame;
+		self.cycle_accumulator = std::time::Duration::new(0, 0);
+		self.timer_accumulator = std::time::Duration::new(0, 0);
 		Ok(())
 	}
+
 	/// Increment PC
 	pub fn inc_pc(&mut self) {
 		self.pc += 2;
@@ -205,22 +402,59 @@ impl System {
 		self.sp += 1;
 	}
 
-	/// Fetch the next Instruction
-	pub fn fetch_instr(&mut self) -> Option<Instruction> {
+	/// Fetch and apply exactly one instruction, returning the decoded
+	/// `Instruction`. Unlike `run`/`tick`, this does no sleeping,
+	/// rendering or timer bookkeeping, which makes it suitable for
+	/// driving the system from a `Debugger`.
+	pub fn step(&mut self) -> Result<Instruction, Chip8Error> {
+		let pc = self.pc;
 		let opcode =
 			((self.mem[self.pc as usize + 0] as u16) << 8) +
 			 self.mem[self.pc as usize + 1] as u16;
 		self.inc_pc();
-		if self.dt != 0 {
-			self.dt -= 1;
+		let instr = Instruction::parse(opcode).map_err(|err| match err {
+			Chip8Error::UnknownOpcode { opcode, .. } => Chip8Error::UnknownOpcode { opcode, pc: Some(pc) },
+			other => other,
+		})?;
+		self.apply(instr);
+		Ok(instr)
+	}
+
+	/// Advance the system by `elapsed` wall-clock time: run as many CPU
+	/// cycles as `cycles_per_frame` implies and decrement `dt`/`st` by
+	/// exactly one every 1/60s, regardless of how many instructions ran in
+	/// that time. Rendering is the caller's responsibility, not `tick`'s.
+	pub fn tick(&mut self, elapsed: std::time::Duration) -> Result<(), Chip8Error> {
+		self.tick_with(elapsed, |system| system.step().map(|_| ()))
+	}
+
+	/// Like `tick`, but runs a cycle through `dispatch` instead of always
+	/// fetching and decoding one instruction via `step`. This is what lets
+	/// `run_jit` reuse the same cycle/timer pacing as `run` while dispatching
+	/// through a `Jit` instead.
+	fn tick_with<D>(&mut self, elapsed: std::time::Duration, mut dispatch: D) -> Result<(), Chip8Error>
+		where D: FnMut(&mut System) -> Result<(), Chip8Error>
+	{
+		let cycle_period = timer_period() / self.cycles_per_frame.max(1);
+
+		self.cycle_accumulator += elapsed;
+		while self.cycle_accumulator >= cycle_period {
+			self.cycle_accumulator -= cycle_period;
+			dispatch(self)?;
 		}
-		if self.st != 0 {
-			self.st -= 1;
+
+		self.timer_accumulator += elapsed;
+		while self.timer_accumulator >= timer_period() {
+			self.timer_accumulator -= timer_period();
+			if self.dt != 0 {
+				self.dt -= 1;
+			}
+			if self.st != 0 {
+				self.st -= 1;
+			}
 		}
-		print!("{}[2J", 27 as char);
-		std::thread::sleep(std::time::Duration::from_millis(15));
-		println!("{:?}", self.display);
-		Instruction::parse(opcode)
+
+		Ok(())
 	}
 
 	/// Run a instruction
@@ -238,12 +472,25 @@ impl System {
 				let from = self.i as usize;
 				let to   = from + length as usize;
 				let sprite = self.mem[from .. to].as_ref();
+				self.regs[0xf] = 0;
 				for (column, byte) in sprite.iter().enumerate() {
 					for (row, bit) in bits(*byte).iter().enumerate() {
-						let _x = x as usize + row;
-						let _y = y as usize + column;
-						let collision = self.display.xor(_x, _y, *bit);
-						self.regs[0xf] = collision as u8;
+						if !*bit {
+							continue;
+						}
+						let mut _x = x as usize + row;
+						let mut _y = y as usize + column;
+						if self.quirks.clip_quirk {
+							if _x >= Display::HEIGHT || _y >= Display::WIDTH {
+								continue;
+							}
+						} else {
+							_x %= Display::HEIGHT;
+							_y %= Display::WIDTH;
+						}
+						if self.display.xor(_x, _y, *bit) {
+							self.regs[0xf] = 1;
+						}
 					}
 				}
 			}
@@ -267,6 +514,9 @@ impl System {
 				for (i, val) in self.mem.iter().skip(self.i as usize).take(x as usize + 1).enumerate() {
 					self.regs[i] = *val;
 				}
+				if !self.quirks.load_store_quirk {
+					self.i += x as u16 + 1;
+				}
 			}
 			LdSprite(x) => {
 				self.i = x as u16 * 5;
@@ -313,6 +563,56 @@ impl System {
 			}
 			And(x, y) => {
 				self.regs[x as usize] &= self.regs[y as usize];
+				if self.quirks.logic_quirk {
+					self.regs[0xf] = 0;
+				}
+			}
+			Or(x, y) => {
+				self.regs[x as usize] |= self.regs[y as usize];
+				if self.quirks.logic_quirk {
+					self.regs[0xf] = 0;
+				}
+			}
+			Xor(x, y) => {
+				self.regs[x as usize] ^= self.regs[y as usize];
+				if self.quirks.logic_quirk {
+					self.regs[0xf] = 0;
+				}
+			}
+			Shr(x, y) => {
+				if self.quirks.shift_quirk {
+					let lsb = self.regs[x as usize] & 0x1;
+					self.regs[x as usize] >>= 1;
+					self.regs[0xf] = lsb;
+				} else {
+					let lsb = self.regs[y as usize] & 0x1;
+					self.regs[x as usize] = self.regs[y as usize] >> 1;
+					self.regs[0xf] = lsb;
+				}
+			}
+			Shl(x, y) => {
+				if self.quirks.shift_quirk {
+					let msb = (self.regs[x as usize] & 0x80) >> 7;
+					self.regs[x as usize] <<= 1;
+					self.regs[0xf] = msb;
+				} else {
+					let msb = (self.regs[y as usize] & 0x80) >> 7;
+					self.regs[x as usize] = self.regs[y as usize] << 1;
+					self.regs[0xf] = msb;
+				}
+			}
+			Subn(x, y) => {
+				let (val, overflowing) = self.regs[y as usize].overflowing_sub(self.regs[x as usize]);
+				self.regs[x as usize] = val;
+				self.regs[0xf] = if overflowing { 0 } else { 1 };
+			}
+			JpV0(nnn) => {
+				if self.quirks.jump_quirk {
+					let x = ((nnn & 0x0f00) >> 8) as usize;
+					self.pc = nnn + self.regs[x] as u16;
+				} else {
+					self.pc = nnn + self.regs[0] as u16;
+				}
 			}
 			AddCarry(x, y) => {
 				let (val, overflowing) = self.regs[x as usize].overflowing_add(self.regs[y as usize]);
@@ -357,17 +657,57 @@ impl System {
 				for i in 0..x+1 {
 					self.mem[self.i as usize + i as usize] = *self.reg(i);
 				}
+				if !self.quirks.load_store_quirk {
+					self.i += x as u16 + 1;
+				}
 			}
 			_ => (println!("{:?}", instruction))
 		}
 	}
 
-	/// Run the program from memory
-	pub fn run(&mut self) {
+	/// Run the program from memory, ticking the system once per frame and
+	/// rendering the display after each tick
+	pub fn run(&mut self) -> Result<(), Chip8Error> {
 		// Set PC to start
 		self.pc = 0x200;
-		while let Some(ins) = self.fetch_instr() {
-			self.apply(ins);
+		let mut last = std::time::Instant::now();
+		loop {
+			let now = std::time::Instant::now();
+			self.tick(now - last)?;
+			last = now;
+			println!("{:?}", self.display);
+
+			// Pace the outer loop to one frame (1/60s), regardless of
+			// cycles_per_frame, so this doesn't busy-spin a core
+			let frame_elapsed = std::time::Instant::now() - now;
+			if let Some(remaining) = timer_period().checked_sub(frame_elapsed) {
+				std::thread::sleep(remaining);
+			}
+		}
+	}
+
+	/// Run the program from memory through the JIT, compiling and caching
+	/// basic blocks instead of decoding one instruction at a time. Paced the
+	/// same way as `run`: ticked once per frame so `dt`/`st` still decrement
+	/// and the display still renders, instead of spinning the JIT unthrottled
+	#[cfg(feature = "jit")]
+	pub fn run_jit(&mut self) -> Result<(), Chip8Error> {
+		self.pc = 0x200;
+		let mut jit = jit::Jit::new();
+		let mut last = std::time::Instant::now();
+		loop {
+			let now = std::time::Instant::now();
+			let elapsed = now - last;
+			self.tick_with(elapsed, |system| jit.step(system))?;
+			last = now;
+			println!("{:?}", self.display);
+
+			// Pace the outer loop to one frame (1/60s), regardless of
+			// cycles_per_frame, so this doesn't busy-spin a core
+			let frame_elapsed = std::time::Instant::now() - now;
+			if let Some(remaining) = timer_period().checked_sub(frame_elapsed) {
+				std::thread::sleep(remaining);
+			}
 		}
 	}
 }
@@ -375,8 +715,232 @@ impl System {
 fn main() {
 	let mut sys = System::new();
 	sys.fetch_file("./games/PONG").unwrap();
-	sys.run();
-	//println!("{:?}", sys.fetch_instr());
 
+	if std::env::args().any(|arg| arg == "--debug") {
+		run_debugger(sys);
+	} else {
+		sys.run().unwrap();
+	}
+}
+
+/// Drive `system` from an interactive, REPL-style debugger session read from
+/// stdin (`break <addr>`, `step [count]`, `continue`, `regs`, `mem <start> <len>`, ...)
+fn run_debugger(mut system: System) {
+	system.pc = 0x200;
+	let mut debugger = Debugger::new();
+	let stdin = std::io::stdin();
+	let mut line = String::new();
+	loop {
+		line.clear();
+		if stdin.lock().read_line(&mut line).unwrap_or(0) == 0 {
+			break;
+		}
+		let args: Vec<&str> = line.split_whitespace().collect();
+		if args.is_empty() {
+			continue;
+		}
+		debugger.run_command(&mut system, &args);
+	}
+}
+
+#[test]
+fn test_save_and_load_state() {
+	let mut sys = System::new();
+	sys.apply(Instruction::Ld(0, 5));
+	sys.apply(Instruction::LdI(0x300));
+	let _ = sys.step();
+
+	let path = std::env::temp_dir().join("chip8_test_save_and_load_state.bin");
+	sys.save_state(&path).unwrap();
+	let snapshot_regs = sys.regs;
+	let snapshot_i = sys.i;
+	let snapshot_pc = sys.pc;
 
+	// Run a bit further so the live state diverges from the snapshot
+	sys.apply(Instruction::Ld(1, 99));
+	sys.apply(Instruction::LdI(0x400));
+
+	let mut restored = System::new();
+	restored.load_state(&path).unwrap();
+	assert_eq!(restored.regs, snapshot_regs);
+	assert_eq!(restored.i, snapshot_i);
+	assert_eq!(restored.pc, snapshot_pc);
+
+	std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_save_and_load_state_round_trips_quirks_and_cycles_per_frame() {
+	let mut sys = System::new();
+	sys.quirks = Quirks::schip();
+	sys.cycles_per_frame = 42;
+
+	let path = std::env::temp_dir().join("chip8_test_save_and_load_quirks.bin");
+	sys.save_state(&path).unwrap();
+
+	let mut restored = System::new();
+	restored.load_state(&path).unwrap();
+	assert_eq!(restored.quirks, Quirks::schip());
+	assert_eq!(restored.cycles_per_frame, 42);
+
+	std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_load_state_rejects_bad_magic() {
+	let path = std::env::temp_dir().join("chip8_test_bad_magic.bin");
+	std::fs::write(&path, b"not a save state").unwrap();
+
+	let mut sys = System::new();
+	assert!(sys.load_state(&path).is_err());
+
+	std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_step_reports_unknown_opcode_with_pc() {
+	let mut sys = System::new();
+	sys.pc = 0x200;
+	// 0x5001 isn't a valid opcode: the low nibble of a 5xy0 instruction must be 0
+	sys.mem[0x200] = 0x50;
+	sys.mem[0x201] = 0x01;
+	match sys.step() {
+		Err(Chip8Error::UnknownOpcode { opcode: 0x5001, pc: Some(0x200) }) => {}
+		other => panic!("expected UnknownOpcode at 0x200, got {:?}", other),
+	}
+}
+
+#[test]
+fn test_fetch_file_rejects_oversized_rom() {
+	let mut sys = System::new();
+	let path = std::env::temp_dir().join("chip8_test_oversized_rom.bin");
+	std::fs::write(&path, vec![0u8; 4096]).unwrap();
+
+	match sys.fetch_file(&path) {
+		Err(Chip8Error::RomTooLarge) => {}
+		other => panic!("expected RomTooLarge, got {:?}", other),
+	}
+
+	std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_shift_quirk_copies_vy_when_disabled() {
+	let mut sys = System::new();
+	sys.quirks = Quirks::cosmac_vip();
+	sys.regs[2] = 0b0000_0011;
+	sys.regs[1] = 0xff;
+	sys.apply(Instruction::Shr(1, 2));
+	assert_eq!(sys.regs[1], 0b0000_0001);
+	assert_eq!(sys.regs[0xf], 1);
+}
+
+#[test]
+fn test_shift_quirk_shifts_vx_in_place_when_enabled() {
+	let mut sys = System::new();
+	sys.quirks = Quirks::schip();
+	sys.regs[1] = 0b0000_0011;
+	sys.regs[2] = 0xff;
+	sys.apply(Instruction::Shr(1, 2));
+	assert_eq!(sys.regs[1], 0b0000_0001);
+	assert_eq!(sys.regs[0xf], 1);
+}
+
+#[test]
+fn test_load_store_quirk_increments_i() {
+	let mut sys = System::new();
+	sys.quirks = Quirks::cosmac_vip();
+	sys.i = 0x300;
+	sys.regs[0] = 1;
+	sys.regs[1] = 2;
+	sys.apply(Instruction::LdStoreV0(1));
+	assert_eq!(sys.i, 0x302);
+}
+
+#[test]
+fn test_load_store_quirk_leaves_i_unchanged() {
+	let mut sys = System::new();
+	sys.quirks = Quirks::schip();
+	sys.i = 0x300;
+	sys.regs[0] = 1;
+	sys.regs[1] = 2;
+	sys.apply(Instruction::LdStoreV0(1));
+	assert_eq!(sys.i, 0x300);
+}
+
+#[test]
+fn test_logic_quirk_resets_vf() {
+	let mut sys = System::new();
+	sys.quirks = Quirks::cosmac_vip();
+	sys.regs[0xf] = 1;
+	sys.regs[1] = 0b1010;
+	sys.regs[2] = 0b0110;
+	sys.apply(Instruction::And(1, 2));
+	assert_eq!(sys.regs[0xf], 0);
+}
+
+#[test]
+fn test_jump_quirk_uses_vx() {
+	let mut sys = System::new();
+	sys.quirks = Quirks::schip();
+	// JpV0(0x200)'s high nibble (0x2) selects V2, not the jump target's V0
+	sys.regs[2] = 0x10;
+	sys.apply(Instruction::JpV0(0x200));
+	assert_eq!(sys.pc, 0x210);
+}
+
+#[test]
+fn test_draw_clip_quirk_discards_offscreen_pixels() {
+	let mut sys = System::new();
+	sys.quirks = Quirks::cosmac_vip();
+	sys.i = 0x300;
+	sys.mem[0x300] = 0xff;
+	// Drawn at x=30, this sprite's bits would run off the bottom edge of the
+	// (32-tall) display; the clip quirk should simply drop those pixels
+	// rather than panic on an out-of-bounds index.
+	sys.apply(Instruction::Drw(30, 0, 1));
+	assert!(sys.display.arr[30][0]);
+	assert!(sys.display.arr[31][0]);
+}
+
+#[test]
+fn test_draw_wraps_when_clip_quirk_disabled() {
+	let mut sys = System::new();
+	sys.quirks = Quirks::schip();
+	sys.i = 0x300;
+	sys.mem[0x300] = 0xff;
+	sys.apply(Instruction::Drw(30, 0, 1));
+	// Bits that run past the bottom edge wrap back around to the top
+	assert!(sys.display.arr[0][0]);
+}
+
+#[test]
+fn test_tick_decrements_timers_at_60hz_regardless_of_cycles_per_frame() {
+	let mut sys = System::new();
+	sys.pc = 0x200;
+	sys.cycles_per_frame = 100;
+	sys.dt = 2;
+	sys.st = 2;
+	// One 1/60s frame should drop both timers by exactly one, no matter
+	// how many instructions `cycles_per_frame` ran in that time
+	sys.tick(timer_period()).unwrap();
+	assert_eq!(sys.dt, 1);
+	assert_eq!(sys.st, 1);
+	sys.tick(timer_period()).unwrap();
+	assert_eq!(sys.dt, 0);
+	assert_eq!(sys.st, 0);
+}
+
+#[test]
+fn test_tick_runs_cycles_per_frame_instructions_per_frame() {
+	let mut sys = System::new();
+	sys.cycles_per_frame = 4;
+	sys.pc = 0x200;
+	// Four LD V0, 0x01 instructions back to back
+	for addr in (0x200..0x208).step_by(2) {
+		sys.mem[addr] = 0x60;
+		sys.mem[addr + 1] = 0x01;
+	}
+	sys.tick(timer_period()).unwrap();
+	assert_eq!(sys.pc, 0x208);
 }